@@ -1,16 +1,31 @@
 use crate::{model::{State, BotLevel}, rules::{face_score, REROLL_EV}};
 use rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
-pub struct BotDecision { pub pick_indices: Vec<usize> }
+pub struct BotDecision {
+    pub pick_indices: Vec<usize>,
+    /// EV-minimizing additional score for the chosen subset, when no `leader_to_beat` is set.
+    /// Only the `Optimal` policy fills this in; the other policies leave it `None`.
+    pub expected_value: Option<f32>,
+    /// P(final additional score keeps the player at or under `leader_to_beat`), when set.
+    /// Only the `Optimal` policy fills this in; the other policies leave it `None`.
+    pub win_probability: Option<f32>,
+}
 
 pub fn bot_pick(state: &State, level: BotLevel, rng: &mut StdRng) -> BotDecision {
-    match level { BotLevel::Amateur => amateur_policy(state, rng), BotLevel::Pro => pro_policy(state, rng) }
+    match level {
+        BotLevel::Amateur => amateur_policy(state, rng),
+        BotLevel::Pro => pro_policy(state, rng),
+        BotLevel::Optimal => pro_optimal_policy(state, rng),
+        BotLevel::Learned => learned_policy(state, &DEFAULT_LEARNED_WEIGHTS, rng),
+    }
 }
 
 pub(crate) fn amateur_policy(state: &State, rng: &mut StdRng) -> BotDecision {
     let faces = &state.last_faces;
     let mut threes: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f == 3).map(|(i,_)| i).collect();
-    if !threes.is_empty() { return BotDecision { pick_indices: threes }; }
+    if !threes.is_empty() { return BotDecision { pick_indices: threes, expected_value: None, win_probability: None }; }
     let mut lowest = 0usize;
     for i in 1..faces.len() {
         let a = face_score(faces[lowest]); let b = face_score(faces[i]);
@@ -26,13 +41,13 @@ pub(crate) fn amateur_policy(state: &State, rng: &mut StdRng) -> BotDecision {
         if ev_line <= leader { picks.extend(extra_ones.drain(..)); }
     }
     if faces.len() > 1 && rng.gen::<u8>() % 5 == 0 { picks.reverse(); }
-    BotDecision { pick_indices: picks }
+    BotDecision { pick_indices: picks, expected_value: None, win_probability: None }
 }
 
 fn pro_policy(state: &State, _rng: &mut StdRng) -> BotDecision {
     let faces = &state.last_faces;
     let mut picks: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f == 3).map(|(i,_)| i).collect();
-    if !picks.is_empty() { return BotDecision { pick_indices: picks }; }
+    if !picks.is_empty() { return BotDecision { pick_indices: picks, expected_value: None, win_probability: None }; }
     let leader = state.leader_to_beat.unwrap_or(u32::MAX);
     let current_total: u32 = state.players[state.turn_idx].picks.iter().map(|&v| v as u32).sum();
     let mut ones: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f == 1).map(|(i,_)| i).collect();
@@ -41,17 +56,261 @@ fn pro_policy(state: &State, _rng: &mut StdRng) -> BotDecision {
         + (crate::rules::REROLL_EV * remaining_if_bank_ones as f32).round() as u32;
     if ev_line_all_ones <= leader && !ones.is_empty() {
         picks.append(&mut ones);
-        return BotDecision { pick_indices: picks };
+        return BotDecision { pick_indices: picks, expected_value: None, win_probability: None };
     }
     let mut lowest = 0usize;
     for i in 1..faces.len() {
         let a = face_score(faces[lowest]); let b = face_score(faces[i]);
         if b < a { lowest = i; }
     }
-    BotDecision { pick_indices: vec![lowest] }
+    BotDecision { pick_indices: vec![lowest], expected_value: None, win_probability: None }
 }
 
 // public re-export for timeout fallback to call without exposing internals
 pub fn amateur_policy_public(state: &State, rng: &mut StdRng) -> Vec<usize> {
     amateur_policy(state, rng).pick_indices
 }
+
+// ---- BotLevel::Optimal: exact expectiminimax over the dice-count state space ----
+//
+// V(0) = 0; for d >= 1, V(d) is the expectation over the 6^d roll outcomes (collapsed to
+// face-multiset counts, weighted by their multinomial coefficients) of the best achievable
+// (banked score + V(remaining)). Because banking a die of a given face value always costs
+// the same regardless of which other dice are banked, the optimal nonempty subset for a
+// given roll is always a prefix of the non-3 dice sorted ascending by value (bank every 3
+// for free, plus however many of the cheapest remaining dice minimize cost + V(remaining)) —
+// so `best_bank_plan` below checks every prefix length directly against `ev` rather than
+// walking a marginal threshold, which would otherwise need to compare against `ev[d]` while
+// `ev[d]` is itself still being computed.
+
+const MAX_DICE: usize = 7;
+
+struct OptimalTables {
+    ev: [f32; MAX_DICE + 1],
+    dist: [Vec<f64>; MAX_DICE + 1],
+}
+
+fn factorial(n: usize) -> f64 { (1..=n).map(|x| x as f64).product() }
+
+fn multinomial_weight(d: usize, counts: &[usize; 6]) -> f64 {
+    let mut denom = 1.0f64;
+    for &c in counts { denom *= factorial(c); }
+    factorial(d) / denom / 6f64.powi(d as i32)
+}
+
+/// All distinct face-count multisets for `d` dice, each paired with its roll probability.
+fn face_count_outcomes(d: usize) -> Vec<([usize; 6], f64)> {
+    let mut out = Vec::new();
+    let mut counts = [0usize; 6];
+    fn rec(remaining: usize, face: usize, d: usize, counts: &mut [usize; 6], out: &mut Vec<([usize; 6], f64)>) {
+        if face == 5 {
+            counts[5] = remaining;
+            out.push((*counts, multinomial_weight(d, counts)));
+            counts[5] = 0;
+            return;
+        }
+        for c in 0..=remaining {
+            counts[face] = c;
+            rec(remaining - c, face + 1, d, counts, out);
+        }
+        counts[face] = 0;
+    }
+    rec(d, 0, d, &mut counts, &mut out);
+    out
+}
+
+/// Given the face counts of a roll and the EV table for strictly smaller dice counts, return
+/// the (banked score, remaining dice) of the EV-minimizing bank decision. All 3s are always
+/// banked (free); beyond that every prefix length of the sorted non-3 dice is evaluated
+/// directly against `cost + ev[remaining]`, so this never needs to consult `ev` at the dice
+/// count currently being solved for.
+fn best_bank_plan(counts: &[usize; 6], ev: &[f32]) -> (u32, usize) {
+    let threes = counts[2];
+    let mut dice: Vec<u8> = Vec::with_capacity(counts.iter().sum());
+    for (idx, &c) in counts.iter().enumerate() {
+        let face = (idx + 1) as u8;
+        if face == 3 { continue; }
+        for _ in 0..c { dice.push(face_score(face)); }
+    }
+    dice.sort_unstable();
+
+    // If there are no 3s, at least one non-3 die must be banked (must_pick_at_least_one).
+    let min_extra = if threes == 0 { 1 } else { 0 };
+    let mut prefix_cost = 0u32;
+    let mut best_cost = 0u32;
+    let mut best_remaining = dice.len();
+    let mut best_total = f32::MAX;
+    for j in 0..=dice.len() {
+        if j >= min_extra {
+            let remaining = dice.len() - j;
+            let total = prefix_cost as f32 + ev[remaining];
+            if total < best_total {
+                best_total = total;
+                best_cost = prefix_cost;
+                best_remaining = remaining;
+            }
+        }
+        if j < dice.len() { prefix_cost += dice[j] as u32; }
+    }
+    (best_cost, best_remaining)
+}
+
+fn build_optimal_tables() -> OptimalTables {
+    let mut ev = [0f32; MAX_DICE + 1];
+    let mut dist: [Vec<f64>; MAX_DICE + 1] = Default::default();
+    dist[0] = vec![1.0];
+    for d in 1..=MAX_DICE {
+        let outcomes = face_count_outcomes(d);
+        let mut total_ev = 0f32;
+        let mut distribution = vec![0f64; d * 6 + 1];
+        for (counts, weight) in &outcomes {
+            let (cost, remaining) = best_bank_plan(counts, &ev);
+            total_ev += (*weight as f32) * (cost as f32 + ev[remaining]);
+            for (k, &p) in dist[remaining].iter().enumerate() {
+                if p != 0.0 { distribution[cost as usize + k] += weight * p; }
+            }
+        }
+        ev[d] = total_ev;
+        dist[d] = distribution;
+    }
+    OptimalTables { ev, dist }
+}
+
+fn optimal_tables() -> &'static OptimalTables {
+    static TABLES: OnceLock<OptimalTables> = OnceLock::new();
+    TABLES.get_or_init(build_optimal_tables)
+}
+
+fn pro_optimal_policy(state: &State, _rng: &mut StdRng) -> BotDecision {
+    let faces = &state.last_faces;
+    let t = optimal_tables();
+    let threes: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f == 3).map(|(i, _)| i).collect();
+    let mut nonthrees: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f != 3).map(|(i, _)| i).collect();
+    nonthrees.sort_by_key(|&i| face_score(faces[i]));
+    let current_total: u32 = state.players[state.turn_idx].picks.iter().map(|&v| v as u32).sum();
+
+    if let Some(leader) = state.leader_to_beat {
+        let mut best_k = 0usize;
+        let mut best_cost = 0u32;
+        let mut best_prob = -1f64;
+        for k in 0..=nonthrees.len() {
+            if threes.is_empty() && k == 0 { continue; }
+            let cost: u32 = nonthrees[..k].iter().map(|&i| face_score(faces[i]) as u32).sum();
+            let remaining = faces.len() - threes.len() - k;
+            let budget = leader as i64 - current_total as i64 - cost as i64;
+            let prob = if budget < 0 { 0.0 } else { t.dist[remaining].iter().take(budget as usize + 1).sum::<f64>() };
+            if prob > best_prob || (prob == best_prob && cost < best_cost) {
+                best_prob = prob; best_k = k; best_cost = cost;
+            }
+        }
+        let mut picks = threes;
+        picks.extend_from_slice(&nonthrees[..best_k]);
+        return BotDecision { pick_indices: picks, expected_value: None, win_probability: Some(best_prob.max(0.0) as f32) };
+    }
+
+    let mut best_k = 0usize;
+    let mut best_total = f32::MAX;
+    for k in 0..=nonthrees.len() {
+        if threes.is_empty() && k == 0 { continue; }
+        let cost: u32 = nonthrees[..k].iter().map(|&i| face_score(faces[i]) as u32).sum();
+        let remaining = faces.len() - threes.len() - k;
+        let total = cost as f32 + t.ev[remaining];
+        if total < best_total { best_total = total; best_k = k; }
+    }
+    let mut picks = threes;
+    picks.extend_from_slice(&nonthrees[..best_k]);
+    BotDecision { pick_indices: picks, expected_value: Some(best_total), win_probability: None }
+}
+
+// ---- BotLevel::Learned: weight-parameterized policy tuned by `crate::train` ----
+
+/// Weights a `Learned` bot uses to decide which dice to bank. Tunable by `crate::train::train`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct LearnedWeights {
+    /// Per-face-value preference for banking that face (index 0 = face 1, ..., index 5 = face 6).
+    /// Higher means "more willing to keep".
+    pub keep_face: [f32; 6],
+    /// Weight on the marginal value of leaving one more die in the reroll pool (scales `REROLL_EV`).
+    pub reroll_ev_weight: f32,
+    /// Added to every keep score while behind `leader_to_beat`, pushing the bot to bank more.
+    pub aggression: f32,
+    /// Scales appetite for variance: positive shades toward banking fewer dice for a shot at a
+    /// much lower score, negative shades toward locking in a safe total.
+    pub risk: f32,
+}
+
+/// Placeholder weights; regenerate with `train::train(...)` and paste the result in here.
+pub const DEFAULT_LEARNED_WEIGHTS: LearnedWeights = LearnedWeights {
+    keep_face: [0.9, 0.3, 0.0, -0.1, -0.4, -0.7],
+    reroll_ev_weight: 1.0,
+    aggression: 0.5,
+    risk: 0.0,
+};
+
+pub(crate) fn learned_policy(state: &State, weights: &LearnedWeights, rng: &mut StdRng) -> BotDecision {
+    let faces = &state.last_faces;
+    let threes: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f == 3).map(|(i, _)| i).collect();
+    let mut nonthrees: Vec<usize> = faces.iter().enumerate().filter(|(_, &f)| f != 3).map(|(i, _)| i).collect();
+    nonthrees.sort_by(|&a, &b| {
+        let wa = weights.keep_face[faces[a] as usize - 1];
+        let wb = weights.keep_face[faces[b] as usize - 1];
+        wb.partial_cmp(&wa).unwrap()
+    });
+
+    let current_total: u32 = state.players[state.turn_idx].picks.iter().map(|&v| v as u32).sum();
+    let behind = state.leader_to_beat.map(|l| current_total > l.saturating_sub(REROLL_EV as u32)).unwrap_or(false);
+    let push = if behind { weights.aggression } else { 0.0 };
+
+    let mut picks = threes;
+    for &i in &nonthrees {
+        let remaining_after = state.remaining_dice - picks.len() - 1;
+        let score = weights.keep_face[faces[i] as usize - 1] - weights.reroll_ev_weight * REROLL_EV
+            + push - weights.risk * remaining_after as f32;
+        if score > 0.0 { picks.push(i); } else { break; }
+    }
+    if picks.is_empty() { picks.push(nonthrees[0]); }
+    if faces.len() > 1 && rng.gen::<u8>() % 11 == 0 { picks.reverse(); }
+    BotDecision { pick_indices: picks, expected_value: None, win_probability: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Brute-force V(d) by exhaustively enumerating all 6^d outcomes and every nonempty
+    /// subset of each, independent of `best_bank_plan`'s prefix-only search — a reference
+    /// to catch regressions like the one where the table read its own not-yet-finalized
+    /// entry (`ev[d]`) while computing `ev[d]`.
+    fn brute_force_ev(d: usize, ev_smaller: &[f32]) -> f32 {
+        let outcomes = 6usize.pow(d as u32);
+        let mut total = 0f32;
+        for outcome in 0..outcomes {
+            let mut faces = [0u8; 8];
+            let mut o = outcome;
+            for f in faces.iter_mut().take(d) { *f = (o % 6) as u8 + 1; o /= 6; }
+            let mut best = f32::MAX;
+            for subset in 1u32..(1 << d) {
+                let mut cost = 0u32;
+                let mut picked = 0usize;
+                for (i, &face) in faces.iter().enumerate().take(d) {
+                    if subset & (1 << i) != 0 { cost += face_score(face) as u32; picked += 1; }
+                }
+                let total_for_subset = cost as f32 + ev_smaller[d - picked];
+                if total_for_subset < best { best = total_for_subset; }
+            }
+            total += best;
+        }
+        total / outcomes as f32
+    }
+
+    #[test]
+    fn optimal_ev_matches_brute_force_for_small_dice_counts() {
+        let t = optimal_tables();
+        let mut ev_ref = vec![0f32; 3];
+        ev_ref[1] = brute_force_ev(1, &ev_ref);
+        ev_ref[2] = brute_force_ev(2, &ev_ref);
+
+        assert!((t.ev[1] - ev_ref[1]).abs() < 1e-4, "V(1): table={} brute={}", t.ev[1], ev_ref[1]);
+        assert!((t.ev[2] - ev_ref[2]).abs() < 1e-3, "V(2): table={} brute={}", t.ev[2], ev_ref[2]);
+    }
+}
@@ -1,29 +1,28 @@
 pub mod model;
+pub mod replay;
 pub mod rng;
 pub mod rules;
 pub mod bot;
+pub mod train;
 
 use model::*;
 use rand::{SeedableRng, rngs::StdRng, Rng};
+use replay::hash_state;
 use rules::{face_score, sum_score};
 use serde_json::json;
 
-fn hash_state_stub(s: &State) -> String {
-    format!("h:{}:{}:{}", s.turn_idx, s.remaining_dice, s.players[s.turn_idx].picks.len())
-}
-
-pub fn init_game(seed: u64, mut players: Vec<Player>) -> State {
+pub fn init_game(seed: u64, mut players: Vec<Player>, tie_break: TieBreak) -> State {
     let pot_cents = players.iter().map(|p| p.wager_cents).sum();
     let mut rng = StdRng::seed_from_u64(seed ^ 0x5EED);
     players.shuffle(&mut rng);
     State {
         seed, players, turn_idx: 0, remaining_dice: 7, last_faces: vec![],
         must_pick_at_least_one: true, pot_cents, phase: Phase::Normal,
-        events_seq: 0, per_turn_deadline_ms: None, leader_to_beat: None,
+        events_seq: 0, per_turn_deadline_ms: None, leader_to_beat: None, tie_break,
     }
 }
 
-pub fn roll(state: &mut State) -> Event {
+pub fn roll(state: &mut State, verbose: bool) -> StageResult {
     assert!(matches!(state.phase, Phase::Normal));
     assert!(state.remaining_dice > 0);
     let mut rng = StdRng::seed_from_u64(state.seed ^ (state.events_seq as u64).wrapping_mul(7919));
@@ -31,23 +30,30 @@ pub fn roll(state: &mut State) -> Event {
     for _ in 0..state.remaining_dice { faces.push(((rng.gen::<u8>() % 6) + 1) as u8); }
     state.last_faces = faces;
     state.must_pick_at_least_one = true;
+    state.players[state.turn_idx].roll_count += 1;
     state.events_seq += 1;
-    Event { seq: state.events_seq, ty: EventType::Roll, payload: json!({ "faces": state.last_faces }), state_hash: hash_state_stub(state) }
+    let event = Event { seq: state.events_seq, ty: EventType::Roll, payload: json!({ "faces": state.last_faces }), state_hash: hash_state(state) };
+    let logs = if verbose { vec![format!("dice showed: {:?}", state.last_faces)] } else { vec![] };
+    StageResult { event, title: "Roll".to_string(), logs }
 }
 
-pub fn pick(state: &mut State, indices: &[usize]) -> Event {
+pub fn pick(state: &mut State, indices: &[usize], verbose: bool) -> StageResult {
     assert!(!state.last_faces.is_empty());
     assert!(!indices.is_empty());
     let mut idxs = indices.to_vec();
     idxs.sort_unstable(); idxs.dedup();
+    let mut picked_faces: Vec<u8> = Vec::with_capacity(idxs.len());
     let mut picked_vals: Vec<u8> = Vec::with_capacity(idxs.len());
     for (k, &i) in idxs.iter().enumerate() {
         assert!(i < state.last_faces.len(), "index OOB at {}", k);
+        picked_faces.push(state.last_faces[i]);
         picked_vals.push(face_score(state.last_faces[i]));
     }
     let p = &mut state.players[state.turn_idx];
     p.picks.extend(picked_vals.iter().copied());
     p.total_score = sum_score(&p.picks) as u32;
+    let total = p.total_score;
+    p.turn_totals.push(total);
     let mut remaining: Vec<u8> = Vec::with_capacity(state.last_faces.len() - idxs.len());
     for (i, &f) in state.last_faces.iter().enumerate() {
         if !idxs.contains(&i) { remaining.push(f); }
@@ -56,41 +62,137 @@ pub fn pick(state: &mut State, indices: &[usize]) -> Event {
     state.last_faces.clear();
     state.must_pick_at_least_one = false;
     state.events_seq += 1;
-    Event { seq: state.events_seq, ty: EventType::Pick, payload: json!({ "picked": idxs, "values": picked_vals }), state_hash: hash_state_stub(state) }
+    let leader = state.leader_to_beat;
+    let event = Event { seq: state.events_seq, ty: EventType::Pick, payload: json!({ "picked": idxs, "values": picked_vals }), state_hash: hash_state(state) };
+    let logs = if verbose {
+        vec![
+            format!("banked dice showing {picked_faces:?}, scored {picked_vals:?} (3s score 0)"),
+            format!("running total: {total}"),
+            match leader {
+                Some(l) => format!("leader to beat: {l}"),
+                None => "leader to beat: none".to_string(),
+            },
+        ]
+    } else { vec![] };
+    StageResult { event, title: "Pick".to_string(), logs }
 }
 
-pub fn end_turn_if_done(state: &mut State) -> Option<Event> {
+pub fn end_turn_if_done(state: &mut State, verbose: bool) -> Option<StageResult> {
     if state.remaining_dice == 0 {
         state.events_seq += 1;
-        let total = state.players[state.turn_idx].total_score;
-        let ev = Event { seq: state.events_seq, ty: EventType::EndTurn, payload: json!({ "playerIdx": state.turn_idx, "total": total }), state_hash: hash_state_stub(state) };
+        let ended_idx = state.turn_idx;
+        let total = state.players[ended_idx].total_score;
         let next_idx = (state.turn_idx + 1) % state.players.len();
         let last_player = state.turn_idx == state.players.len() - 1;
         state.turn_idx = next_idx; state.remaining_dice = 7;
         state.last_faces.clear(); state.must_pick_at_least_one = true;
+
+        let mut ty = EventType::EndTurn;
+        let mut winner: Option<PlayerId> = None;
+        let mut outcome_log: Option<String> = None;
         if last_player {
-            // compute winner(s)
             let mut lows: Vec<usize> = vec![]; let mut low = u32::MAX;
             for (i, pl) in state.players.iter().enumerate() {
                 if pl.total_score < low { low = pl.total_score; lows.clear(); lows.push(i); }
                 else if pl.total_score == low { lows.push(i); }
             }
-            if lows.len() > 1 { state.phase = Phase::SuddenDeath(lows.iter().map(|&i| state.players[i].id.clone()).collect()); }
-            else { state.phase = Phase::Finished; }
+            if lows.len() > 1 {
+                match state.tie_break {
+                    TieBreak::SuddenDeath => {
+                        state.phase = Phase::SuddenDeath(lows.iter().map(|&i| state.players[i].id.clone()).collect());
+                        outcome_log = Some(format!("tie for low score among {} players; heading to sudden death", lows.len()));
+                    }
+                    other => {
+                        let winner_idx = resolve_tie(state, &lows, other);
+                        state.phase = Phase::Finished;
+                        winner = Some(state.players[winner_idx].id.clone());
+                        ty = EventType::GameEnd;
+                        outcome_log = Some(format!("tie resolved via {:?}; winner: {}", other, state.players[winner_idx].id));
+                    }
+                }
+            } else {
+                state.phase = Phase::Finished;
+                winner = Some(state.players[lows[0]].id.clone());
+                ty = EventType::GameEnd;
+                outcome_log = Some(format!("game finished; winner: {}", state.players[lows[0]].id));
+            }
         }
-        Some(ev)
+        let event = Event {
+            seq: state.events_seq, ty,
+            payload: json!({ "playerIdx": ended_idx, "total": total, "winner": winner }),
+            state_hash: hash_state(state),
+        };
+        let logs = if verbose {
+            let mut l = vec![format!("player {ended_idx} ended turn with running total {total}")];
+            l.extend(outcome_log);
+            l
+        } else { vec![] };
+        Some(StageResult { event, title: "EndTurn".to_string(), logs })
     } else { None }
 }
 
-pub fn timeout_autoplay(state: &mut State) -> Event {
+/// Resolve a tie among `lows` (player indices) using the given non-sudden-death method.
+fn resolve_tie(state: &State, lows: &[usize], method: TieBreak) -> usize {
+    match method {
+        TieBreak::SuddenDeath => unreachable!("sudden death is handled by the caller"),
+        TieBreak::Countback => {
+            // Walk backwards from each player's own most recent pick, comparing by
+            // position-from-the-end rather than a shared absolute index: tied players can
+            // have picked a different number of times during their turn, so their
+            // `turn_totals` lengths may differ.
+            let max_checkpoints = lows.iter().map(|&i| state.players[i].turn_totals.len()).max().unwrap_or(0);
+            for step in 0..max_checkpoints {
+                let mut candidates: Vec<(usize, u32)> = Vec::new();
+                for &i in lows {
+                    let totals = &state.players[i].turn_totals;
+                    if let Some(idx) = totals.len().checked_sub(step + 1) {
+                        candidates.push((i, totals[idx]));
+                    }
+                }
+                let Some(&min_val) = candidates.iter().map(|(_, v)| v).min() else { continue };
+                let mut winners = candidates.iter().filter(|&&(_, v)| v == min_val).map(|&(i, _)| i);
+                let first = winners.next().expect("min_val came from this iterator");
+                if winners.next().is_none() { return first; }
+            }
+            lows[0]
+        }
+        TieBreak::RandomSeeded => {
+            let mut rng = StdRng::seed_from_u64(state.seed ^ 0xC1E_B8EA_u64);
+            lows[rng.gen_range(0..lows.len())]
+        }
+        TieBreak::FewestRolls => {
+            let mut best = lows[0];
+            for &i in &lows[1..] {
+                if state.players[i].roll_count < state.players[best].roll_count { best = i; }
+            }
+            best
+        }
+    }
+}
+
+pub fn timeout_autoplay(state: &mut State, verbose: bool) -> StageResult {
     let mut rng = StdRng::seed_from_u64(state.seed ^ (state.events_seq as u64).wrapping_mul(104729));
     state.leader_to_beat = compute_leader_to_beat(state);
-    let decision = crate::bot::amateur_policy_public(state, &mut rng);
-    let ev = pick(state, &decision);
-    Event { seq: ev.seq, ty: EventType::TimeoutAutoplay, payload: serde_json::json!({ "chosen": ev.payload, "policy": "amateur_v1" }), state_hash: ev.state_hash.clone() }
+    let decision = crate::bot::amateur_policy(state, &mut rng);
+    let stage = pick(state, &decision.pick_indices, verbose);
+    let event = Event {
+        seq: stage.event.seq, ty: EventType::TimeoutAutoplay,
+        payload: json!({ "chosen": stage.event.payload, "policy": "amateur_v1" }),
+        state_hash: stage.event.state_hash.clone(),
+    };
+    let mut logs = stage.logs;
+    if verbose {
+        logs.push("policy: amateur_v1".to_string());
+        logs.push(match (decision.expected_value, decision.win_probability) {
+            (Some(ev), _) => format!("ev line: {ev:.2}"),
+            (_, Some(p)) => format!("win probability: {:.1}%", p * 100.0),
+            _ => "ev line: n/a".to_string(),
+        });
+    }
+    StageResult { event, title: "TimeoutAutoplay".to_string(), logs }
 }
 
-fn compute_leader_to_beat(state: &State) -> Option<u32> {
+pub(crate) fn compute_leader_to_beat(state: &State) -> Option<u32> {
     let cur_id = state.players[state.turn_idx].id.clone();
     let mut best = u32::MAX; let mut found = false;
     for p in &state.players {
@@ -100,3 +202,132 @@ fn compute_leader_to_beat(state: &State) -> Option<u32> {
     }
     if found { Some(best) } else { None }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tied_player(id: &str, turn_totals: Vec<u32>, roll_count: u32) -> Player {
+        Player {
+            id: id.to_string(), display: id.to_string(), is_bot: true, bot_level: None,
+            wager_cents: 0, total_score: turn_totals.last().copied().unwrap_or(0),
+            picks: vec![], turn_totals, roll_count,
+        }
+    }
+
+    fn state_with(players: Vec<Player>, seed: u64, tie_break: TieBreak) -> State {
+        State {
+            seed, players, turn_idx: 0, remaining_dice: 0, last_faces: vec![],
+            must_pick_at_least_one: true, pot_cents: 0, phase: Phase::Normal,
+            events_seq: 0, per_turn_deadline_ms: None, leader_to_beat: None, tie_break,
+        }
+    }
+
+    #[test]
+    fn countback_picks_the_lower_most_recent_checkpoint() {
+        // Both tied at 10 overall; one checkpoint back, "a" stood at 4 and "b" at 6, so "a" wins.
+        let players = vec![
+            tied_player("a", vec![4, 10], 2),
+            tied_player("b", vec![6, 10], 2),
+        ];
+        let state = state_with(players, 1, TieBreak::Countback);
+        assert_eq!(resolve_tie(&state, &[0, 1], TieBreak::Countback), 0);
+    }
+
+    #[test]
+    fn countback_keeps_comparing_backwards_past_equal_checkpoints() {
+        // Most recent checkpoints tie (10 == 10); the next-most-recent pick breaks it.
+        let players = vec![
+            tied_player("a", vec![5, 10], 2),
+            tied_player("b", vec![3, 10], 2),
+        ];
+        let state = state_with(players, 1, TieBreak::Countback);
+        assert_eq!(resolve_tie(&state, &[0, 1], TieBreak::Countback), 1);
+    }
+
+    #[test]
+    fn countback_falls_back_to_first_low_index_when_fully_tied() {
+        let players = vec![
+            tied_player("a", vec![10], 1),
+            tied_player("b", vec![10], 1),
+        ];
+        let state = state_with(players, 1, TieBreak::Countback);
+        assert_eq!(resolve_tie(&state, &[0, 1], TieBreak::Countback), 0);
+    }
+
+    #[test]
+    fn fewest_rolls_picks_the_player_with_fewer_roll_events() {
+        let players = vec![
+            tied_player("a", vec![10], 4),
+            tied_player("b", vec![10], 2),
+        ];
+        let state = state_with(players, 1, TieBreak::FewestRolls);
+        assert_eq!(resolve_tie(&state, &[0, 1], TieBreak::FewestRolls), 1);
+    }
+
+    #[test]
+    fn random_seeded_is_deterministic_for_a_given_seed() {
+        let players = vec![tied_player("a", vec![10], 1), tied_player("b", vec![10], 1), tied_player("c", vec![10], 1)];
+        let state = state_with(players, 777, TieBreak::RandomSeeded);
+        let first = resolve_tie(&state, &[0, 1, 2], TieBreak::RandomSeeded);
+        let second = resolve_tie(&state, &[0, 1, 2], TieBreak::RandomSeeded);
+        assert_eq!(first, second);
+        assert!(first < 3);
+    }
+
+    fn basic_player(id: &str) -> Player {
+        Player {
+            id: id.to_string(), display: id.to_string(), is_bot: false, bot_level: None,
+            wager_cents: 0, total_score: 0, picks: vec![], turn_totals: vec![], roll_count: 0,
+        }
+    }
+
+    fn two_player_game(seed: u64) -> State {
+        init_game(seed, vec![basic_player("a"), basic_player("b")], TieBreak::SuddenDeath)
+    }
+
+    #[test]
+    fn roll_logs_are_populated_only_when_verbose() {
+        let mut state = two_player_game(1);
+        assert!(roll(&mut state, false).logs.is_empty());
+        let mut state = two_player_game(1);
+        assert!(!roll(&mut state, true).logs.is_empty());
+    }
+
+    #[test]
+    fn pick_logs_are_populated_only_when_verbose() {
+        let mut state = two_player_game(2);
+        roll(&mut state, false);
+        assert!(pick(&mut state, &[0], false).logs.is_empty());
+
+        let mut state = two_player_game(2);
+        roll(&mut state, false);
+        assert!(!pick(&mut state, &[0], true).logs.is_empty());
+    }
+
+    #[test]
+    fn end_turn_logs_are_populated_only_when_verbose() {
+        let mut state = two_player_game(3);
+        roll(&mut state, false);
+        let all: Vec<usize> = (0..state.last_faces.len()).collect();
+        pick(&mut state, &all, false);
+        assert!(end_turn_if_done(&mut state, false).expect("remaining_dice is 0").logs.is_empty());
+
+        let mut state = two_player_game(3);
+        roll(&mut state, false);
+        let all: Vec<usize> = (0..state.last_faces.len()).collect();
+        pick(&mut state, &all, false);
+        assert!(!end_turn_if_done(&mut state, true).expect("remaining_dice is 0").logs.is_empty());
+    }
+
+    #[test]
+    fn timeout_autoplay_logs_are_populated_only_when_verbose() {
+        let mut state = two_player_game(4);
+        roll(&mut state, false);
+        assert!(timeout_autoplay(&mut state, false).logs.is_empty());
+
+        let mut state = two_player_game(4);
+        roll(&mut state, false);
+        assert!(!timeout_autoplay(&mut state, true).logs.is_empty());
+    }
+}
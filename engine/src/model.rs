@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 pub type PlayerId = String;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub enum BotLevel { Amateur, Pro }
+pub enum BotLevel { Amateur, Pro, Optimal, Learned }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum EventType {
@@ -23,6 +23,17 @@ pub struct Event {
     pub state_hash: String,
 }
 
+/// An `Event` plus a human-readable narrative of the stage that produced it, for front-ends
+/// and replays to show a turn-by-turn transcript without re-deriving it from raw payloads.
+/// Populating `logs` costs extra string formatting, so engine functions only do it when
+/// called with `verbose: true`; otherwise `logs` is empty.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageResult {
+    pub event: Event,
+    pub title: String,
+    pub logs: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Player {
     pub id: PlayerId,
@@ -32,6 +43,12 @@ pub struct Player {
     pub wager_cents: u32,
     pub total_score: u32,
     pub picks: Vec<u8>, // 3s stored as 0
+    /// Running `total_score` checkpoint recorded after every Pick this player makes during
+    /// their turn (the game is a single round, so this is the only source of more than one
+    /// checkpoint per player; `TieBreak::Countback` compares these in reverse order).
+    pub turn_totals: Vec<u32>,
+    /// Number of Roll events this player has consumed so far this game.
+    pub roll_count: u32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -41,6 +58,21 @@ pub enum Phase {
     Finished,
 }
 
+/// How a tie for the low score is resolved once every player has finished their final turn.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Tied players play sudden-death rounds until the tie breaks (the engine's original behavior).
+    SuddenDeath,
+    /// Compare tied players' `turn_totals` pick-by-pick, starting from each player's most
+    /// recent pick and working backwards; the lower score at the first differing checkpoint
+    /// wins.
+    Countback,
+    /// Derive a deterministic winner among the tied players from `State::seed`.
+    RandomSeeded,
+    /// The tied player who reached their total in the fewest Roll events wins.
+    FewestRolls,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct State {
     pub seed: u64,
@@ -54,4 +86,5 @@ pub struct State {
     pub events_seq: u64,
     pub per_turn_deadline_ms: Option<u128>,
     pub leader_to_beat: Option<u32>,
+    pub tie_break: TieBreak,
 }
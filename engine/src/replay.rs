@@ -0,0 +1,165 @@
+use crate::model::*;
+use crate::{end_turn_if_done, init_game, pick, roll, timeout_autoplay};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Canonical SHA-256 hash of a `State`, used as the tamper-evident `state_hash` on every
+/// `Event`. `State`'s fields (and `Player`'s) serialize in fixed declaration order and
+/// `players` is a seated-order `Vec`, so `serde_json::to_string` is already a deterministic
+/// serialization with no reliance on hash-map ordering.
+pub fn hash_state(s: &State) -> String {
+    let canonical = serde_json::to_string(s).expect("State serialization is infallible");
+    let mut hasher = Sha256::new();
+    hasher.update(canonical.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// A complete, replayable record of a game: the seed, the seated player list `init_game`
+/// produced, and every event emitted while it was played.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameLog {
+    pub seed: u64,
+    pub tie_break: TieBreak,
+    pub initial_players: Vec<Player>,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The recomputed `state_hash` at `seq` does not match the one recorded in the log.
+    HashMismatch { seq: u64, expected: String, found: String },
+    /// An event's `ty`/`payload` combination couldn't be replayed (e.g. a Pick with no
+    /// `picked` indices array).
+    MalformedEvent { seq: u64 },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::HashMismatch { seq, expected, found } =>
+                write!(f, "state hash mismatch at seq {seq}: expected {expected}, found {found}"),
+            ReplayError::MalformedEvent { seq } =>
+                write!(f, "malformed event at seq {seq}: could not replay its payload"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Build a `GameLog` from the state as it was immediately after `init_game` (so
+/// `initial_players` reflects the seated order the seed produced) plus every event
+/// collected while the game was played.
+pub fn export_log(initial_state: &State, events: &[Event]) -> GameLog {
+    GameLog {
+        seed: initial_state.seed,
+        tie_break: initial_state.tie_break,
+        initial_players: initial_state.players.clone(),
+        events: events.to_vec(),
+    }
+}
+
+/// Re-run the game from `log.seed`/`log.initial_players`, replaying each recorded event's
+/// action (Roll, Pick's indices, EndTurn, TimeoutAutoplay), and assert the recomputed
+/// `state_hash` matches the stored one at every `seq`. Returns `Ok(())` if the log is a
+/// faithful, untampered record of a real playthrough.
+pub fn verify_log(log: &GameLog) -> Result<(), ReplayError> {
+    let mut state = init_game(log.seed, log.initial_players.clone(), log.tie_break);
+    // init_game reshuffles seating from the seed; the log already recorded the resulting
+    // seated order, so pin it back to that order before replaying.
+    state.players = log.initial_players.clone();
+
+    let mut produced = Vec::with_capacity(log.events.len());
+    for recorded in &log.events {
+        // Replay never needs the narrative logs, only the resulting event hashes.
+        let replayed = match recorded.ty {
+            EventType::Roll => {
+                // Players refresh `leader_to_beat` before each roll, and it is part of the hashed state.
+                state.leader_to_beat = crate::compute_leader_to_beat(&state);
+                roll(&mut state, false).event
+            }
+            EventType::Pick => {
+                let picked = recorded.payload.get("picked")
+                    .and_then(|v| v.as_array())
+                    .ok_or(ReplayError::MalformedEvent { seq: recorded.seq })?;
+                let idxs: Vec<usize> = picked.iter()
+                    .filter_map(|v| v.as_u64().map(|n| n as usize))
+                    .collect();
+                pick(&mut state, &idxs, false).event
+            }
+            EventType::EndTurn | EventType::GameEnd => {
+                end_turn_if_done(&mut state, false).ok_or(ReplayError::MalformedEvent { seq: recorded.seq })?.event
+            }
+            EventType::TimeoutAutoplay => timeout_autoplay(&mut state, false).event,
+            EventType::SuddenDeathRoll => return Err(ReplayError::MalformedEvent { seq: recorded.seq }),
+        };
+        produced.push(replayed);
+    }
+
+    for (recorded, replayed) in log.events.iter().zip(produced.iter()) {
+        if recorded.state_hash != replayed.state_hash {
+            return Err(ReplayError::HashMismatch {
+                seq: recorded.seq,
+                expected: recorded.state_hash.clone(),
+                found: replayed.state_hash.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::bot_pick;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn fresh_player(id: &str, level: BotLevel) -> Player {
+        Player {
+            id: id.to_string(), display: id.to_string(), is_bot: true, bot_level: Some(level),
+            wager_cents: 0, total_score: 0, picks: vec![], turn_totals: vec![], roll_count: 0,
+        }
+    }
+
+    /// Play an Amateur-vs-Pro game to completion (or into sudden death), recording every
+    /// `Event` along the way, and return the initial state plus the events.
+    fn play_full_game(seed: u64) -> (State, Vec<Event>) {
+        let players = vec![fresh_player("a", BotLevel::Amateur), fresh_player("b", BotLevel::Pro)];
+        let mut state = crate::init_game(seed, players, TieBreak::Countback);
+        let initial_state = state.clone();
+        let mut rng = StdRng::seed_from_u64(seed ^ 0x5ADE_5EED);
+        let mut events = Vec::new();
+
+        loop {
+            match state.phase {
+                Phase::Finished => break,
+                Phase::SuddenDeath(_) => break,
+                Phase::Normal => {}
+            }
+            state.leader_to_beat = crate::compute_leader_to_beat(&state);
+            events.push(crate::roll(&mut state, false).event);
+            let level = state.players[state.turn_idx].bot_level.expect("both players are bots");
+            let decision = bot_pick(&state, level, &mut rng);
+            events.push(crate::pick(&mut state, &decision.pick_indices, false).event);
+            if let Some(r) = crate::end_turn_if_done(&mut state, false) { events.push(r.event); }
+        }
+        (initial_state, events)
+    }
+
+    #[test]
+    fn export_then_verify_round_trips_a_full_game() {
+        let (initial_state, events) = play_full_game(42);
+        let log = export_log(&initial_state, &events);
+        verify_log(&log).expect("an untampered log must verify");
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_log() {
+        let (initial_state, events) = play_full_game(7);
+        let mut log = export_log(&initial_state, &events);
+        // Flip a digit in a recorded hash to simulate a doctored log.
+        let first_hash = &mut log.events[0].state_hash;
+        let flipped = if first_hash.starts_with('0') { '1' } else { '0' };
+        first_hash.replace_range(0..1, &flipped.to_string());
+        assert!(matches!(verify_log(&log), Err(ReplayError::HashMismatch { .. })));
+    }
+}
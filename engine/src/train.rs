@@ -0,0 +1,163 @@
+use crate::bot::{bot_pick, learned_policy, LearnedWeights};
+use crate::model::{BotLevel, Phase, Player, TieBreak};
+use crate::{compute_leader_to_beat, end_turn_if_done, init_game, pick, roll};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const SURVIVOR_FRACTION: f32 = 0.3;
+const INITIAL_MUTATION_RATE: f32 = 0.3;
+
+/// Genetic-algorithm tuning for `BotLevel::Learned`: maintain a population of weight
+/// vectors, score each by simulated win rate against the Pro bot (a fresh seeded RNG per
+/// match keeps comparisons fair), keep the fittest `SURVIVOR_FRACTION`, and breed the next
+/// generation by per-gene-averaging crossover (occasionally one parent copied unchanged)
+/// plus Gaussian mutation at a rate that decays linearly to zero over `generations`.
+pub fn train(generations: u32, population: usize, games_per_eval: u32, seed: u64) -> LearnedWeights {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let survivors = (((population as f32) * SURVIVOR_FRACTION).ceil() as usize).clamp(2, population);
+
+    let mut pool: Vec<LearnedWeights> = (0..population).map(|_| random_weights(&mut rng)).collect();
+    for gen in 0..generations {
+        let gen_seed = seed ^ (gen as u64).wrapping_mul(2_654_435_761);
+        let mut scored: Vec<(f32, LearnedWeights)> = pool.iter()
+            .map(|&w| (evaluate(w, games_per_eval, gen_seed), w))
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+        let elite: Vec<LearnedWeights> = scored.iter().take(survivors).map(|&(_, w)| w).collect();
+        let mutation_rate = INITIAL_MUTATION_RATE * (1.0 - gen as f32 / generations.max(1) as f32);
+
+        let mut next = elite.clone();
+        while next.len() < population {
+            let a = elite[rng.gen_range(0..elite.len())];
+            let b = elite[rng.gen_range(0..elite.len())];
+            next.push(mutate(crossover(a, b, &mut rng), mutation_rate, &mut rng));
+        }
+        pool = next;
+    }
+
+    let final_seed = seed ^ 0xF_1A1E;
+    pool.into_iter()
+        .map(|w| (evaluate(w, games_per_eval, final_seed), w))
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+        .map(|(_, w)| w)
+        .expect("population is never empty")
+}
+
+fn random_weights(rng: &mut StdRng) -> LearnedWeights {
+    LearnedWeights {
+        keep_face: std::array::from_fn(|_| rng.gen_range(-1.0f32..1.0)),
+        reroll_ev_weight: rng.gen_range(0.0f32..2.0),
+        aggression: rng.gen_range(-1.0f32..1.0),
+        risk: rng.gen_range(-1.0f32..1.0),
+    }
+}
+
+fn crossover(a: LearnedWeights, b: LearnedWeights, rng: &mut StdRng) -> LearnedWeights {
+    if rng.gen::<u8>() % 3 == 0 {
+        // Take one parent wholesale, for diversity the averaging case lacks.
+        if rng.gen_bool(0.5) { a } else { b }
+    } else {
+        LearnedWeights {
+            keep_face: std::array::from_fn(|i| (a.keep_face[i] + b.keep_face[i]) / 2.0),
+            reroll_ev_weight: (a.reroll_ev_weight + b.reroll_ev_weight) / 2.0,
+            aggression: (a.aggression + b.aggression) / 2.0,
+            risk: (a.risk + b.risk) / 2.0,
+        }
+    }
+}
+
+fn mutate(w: LearnedWeights, rate: f32, rng: &mut StdRng) -> LearnedWeights {
+    LearnedWeights {
+        keep_face: std::array::from_fn(|i| w.keep_face[i] + gaussian(rng) * rate),
+        reroll_ev_weight: (w.reroll_ev_weight + gaussian(rng) * rate).max(0.0),
+        aggression: w.aggression + gaussian(rng) * rate,
+        risk: w.risk + gaussian(rng) * rate,
+    }
+}
+
+/// Standard-normal sample via Box-Muller, to avoid pulling in a distributions crate for one
+/// use site.
+fn gaussian(rng: &mut StdRng) -> f32 {
+    let u1: f32 = rng.gen_range(1e-6f32..1.0);
+    let u2: f32 = rng.gen_range(0.0f32..1.0);
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+fn evaluate(weights: LearnedWeights, games: u32, seed: u64) -> f32 {
+    let wins = (0..games.max(1))
+        .filter(|&g| simulate_match(weights, seed ^ (g as u64).wrapping_mul(40_503)))
+        .count();
+    wins as f32 / games.max(1) as f32
+}
+
+/// Play one Learned-vs-Pro game to completion and report whether Learned strictly won.
+fn simulate_match(weights: LearnedWeights, seed: u64) -> bool {
+    let fresh_player = |id: &str, display: &str, level: BotLevel| Player {
+        id: id.to_string(), display: display.to_string(), is_bot: true, bot_level: Some(level),
+        wager_cents: 0, total_score: 0, picks: vec![], turn_totals: vec![], roll_count: 0,
+    };
+    let players = vec![
+        fresh_player("learned", "Learned", BotLevel::Learned),
+        fresh_player("pro", "Pro", BotLevel::Pro),
+    ];
+    let mut state = init_game(seed, players, TieBreak::SuddenDeath);
+    let mut rng = StdRng::seed_from_u64(seed ^ 0x5ADE_5EED);
+
+    loop {
+        match state.phase {
+            Phase::Finished => break,
+            // A drawn-out sudden death isn't resolved here; count it as no win for Learned.
+            Phase::SuddenDeath(_) => return false,
+            Phase::Normal => {}
+        }
+        state.leader_to_beat = compute_leader_to_beat(&state);
+        roll(&mut state, false);
+        let decision = if state.players[state.turn_idx].id == "learned" {
+            learned_policy(&state, &weights, &mut rng)
+        } else {
+            let level = state.players[state.turn_idx].bot_level.expect("both players are bots");
+            bot_pick(&state, level, &mut rng)
+        };
+        pick(&mut state, &decision.pick_indices, false);
+        end_turn_if_done(&mut state, false);
+    }
+
+    let learned_idx = state.players.iter().position(|p| p.id == "learned").unwrap();
+    let pro_idx = 1 - learned_idx;
+    state.players[learned_idx].total_score < state.players[pro_idx].total_score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn train_runs_to_completion_with_a_tiny_population() {
+        let weights = train(3, 4, 2, 99);
+        assert!(weights.reroll_ev_weight >= 0.0);
+        for w in weights.keep_face {
+            assert!(w.is_finite());
+        }
+    }
+
+    #[test]
+    fn evaluate_is_sensitive_to_the_weights_being_scored() {
+        // If `simulate_match` routed "learned" through a fixed policy instead of `weights`,
+        // these two opposite-extreme genomes would score identically.
+        let keen = LearnedWeights { keep_face: [5.0; 6], reroll_ev_weight: 2.0, aggression: 1.0, risk: 1.0 };
+        let averse = LearnedWeights { keep_face: [-5.0; 6], reroll_ev_weight: 0.0, aggression: -1.0, risk: -1.0 };
+        let a = evaluate(keen, 40, 2024);
+        let b = evaluate(averse, 40, 2024);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn train_is_deterministic_for_a_given_seed() {
+        let a = train(2, 4, 2, 123);
+        let b = train(2, 4, 2, 123);
+        assert_eq!(a.keep_face, b.keep_face);
+        assert_eq!(a.reroll_ev_weight, b.reroll_ev_weight);
+        assert_eq!(a.aggression, b.aggression);
+        assert_eq!(a.risk, b.risk);
+    }
+}